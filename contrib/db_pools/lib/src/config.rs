@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+/// An individual database's configuration.
+///
+/// This type is deserialized from a `databases.db_name` configuration
+/// parameter, as read from Rocket's regular config sources, for use when
+/// initializing a [`Database`](crate::Database).
+///
+/// # Example
+///
+/// ```toml
+/// [default.databases.db_name]
+/// url = "db.sqlite"
+/// min_connections = 64
+/// max_connections = 1024
+/// connect_timeout = 5
+/// idle_timeout = 120
+/// replica_urls = ["db-replica-1.sqlite", "db-replica-2.sqlite"]
+/// replica_strategy = "round-robin"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Connection URL specific to the database backend.
+    pub url: String,
+    /// Minimum number of connections to maintain in the pool.
+    ///
+    /// Not every backend supports this value, and those that do not ignore
+    /// it without error.
+    ///
+    /// **Note:** `deadpool` and `diesel` drivers do not support and thus
+    /// ignore this value.
+    pub min_connections: Option<u32>,
+    /// Maximum number of connections to maintain in the pool.
+    pub max_connections: usize,
+    /// Number of seconds to wait before timing out when acquiring a
+    /// connection from the pool.
+    ///
+    /// **Note:** the `deadpool` driver ignores this value.
+    pub connect_timeout: u64,
+    /// Maximum number of seconds to keep a connection alive for before
+    /// closing it.
+    ///
+    /// A value of `0` disables this feature.
+    ///
+    /// **Note:** the `deadpool` driver ignores this value.
+    pub idle_timeout: Option<u64>,
+    /// URLs of read-only replicas of `url`, the primary.
+    ///
+    /// When non-empty, [`ReadConnection<D>`](crate::ReadConnection) draws
+    /// connections from a pool built over these URLs instead of the
+    /// primary, selected according to `replica_strategy`. Defaults to
+    /// empty, in which case `ReadConnection<D>` falls back to the primary.
+    #[serde(default)]
+    pub replica_urls: Vec<String>,
+    /// Strategy used to select a replica in [`ReadConnection<D>`](crate::ReadConnection).
+    ///
+    /// Defaults to [`ReplicaStrategy::RoundRobin`].
+    #[serde(default)]
+    pub replica_strategy: ReplicaStrategy,
+    /// Number of seconds between background liveness checks performed by a
+    /// [`HealthMonitor`](crate::HealthMonitor) fairing attached for this
+    /// database.
+    ///
+    /// Defaults to `None`, and a value of `0` also disables the feature
+    /// (matching `idle_timeout`'s convention), in which case no background
+    /// checks are scheduled; [`Database::health()`](crate::Database::health)
+    /// and the health endpoint still check on demand.
+    #[serde(default)]
+    pub health_check_interval: Option<u64>,
+}
+
+/// Strategy for selecting among configured read replicas.
+///
+/// See [`Config::replica_strategy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReplicaStrategy {
+    /// Cycle through healthy replicas in turn.
+    #[default]
+    RoundRobin,
+    /// Prefer the healthy replica with the fewest connections currently
+    /// checked out of its pool.
+    LeastConnections,
+}
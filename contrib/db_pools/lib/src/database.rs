@@ -0,0 +1,287 @@
+use std::ops::{Deref, DerefMut};
+use std::marker::PhantomData;
+
+use rocket::figment::{providers::Serialized, Figment};
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{Build, Ignite, Rocket, Sentinel};
+
+use crate::health::Health;
+use crate::pool::Replicas;
+use crate::{Config, Error, Pool};
+
+/// Trait implemented by database types that are backed by a [`Pool`].
+///
+/// Implemented automatically by `#[derive(Database)]`. See the [crate
+/// docs](crate#quickstart) for usage details.
+#[rocket::async_trait]
+pub trait Database: From<Self::Pool> + DerefMut<Target = Self::Pool> + Send + Sync + 'static {
+    /// The configuration key under `databases` this type is configured with.
+    const NAME: &'static str;
+
+    /// The [`Pool`] type this database wraps.
+    type Pool: Pool;
+
+    /// Returns a fairing that initializes the connection pool for this
+    /// database and, on shutdown, waits for the pool to close.
+    fn init() -> Initializer<Self> {
+        Initializer::new()
+    }
+
+    /// Returns a reference to the inner connection pool.
+    fn pool(&self) -> &Self::Pool {
+        self
+    }
+
+    /// Checks this database's pool now, returning its health.
+    ///
+    /// This performs an on-demand [`Pool::ping()`] and reads
+    /// [`Pool::status()`]; it does not depend on a
+    /// [`HealthMonitor`](crate::HealthMonitor) fairing being attached,
+    /// though one can be attached in addition to track and expose this
+    /// information over HTTP.
+    async fn health(&self) -> Health {
+        Health {
+            name: Self::NAME,
+            healthy: self.pool().ping().await.is_ok(),
+            status: self.pool().status(),
+            last_success_secs_ago: None,
+        }
+    }
+}
+
+/// A request guard that retrieves a single connection from `D`'s pool.
+///
+/// Unlike `&D`, which provides access to the entire pool, retrieving this
+/// guard guarantees that a connection is readily available.
+pub struct Connection<D: Database> {
+    connection: <D::Pool as Pool>::Connection,
+    _database: PhantomData<D>,
+}
+
+impl<D: Database> Connection<D> {
+    pub(crate) fn new(connection: <D::Pool as Pool>::Connection) -> Self {
+        Connection { connection, _database: PhantomData }
+    }
+
+    pub(crate) fn into_inner(self) -> <D::Pool as Pool>::Connection {
+        self.connection
+    }
+}
+
+impl<D: Database> Deref for Connection<D> {
+    type Target = <D::Pool as Pool>::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.connection
+    }
+}
+
+impl<D: Database> DerefMut for Connection<D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.connection
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, D: Database> FromRequest<'r> for Connection<D> {
+    type Error = Error<<D::Pool as Pool>::Error>;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match req.rocket().state::<D>() {
+            Some(db) => match db.pool().get().await {
+                Ok(conn) => Outcome::Success(Connection::new(conn)),
+                Err(e) => Outcome::Error((Status::ServiceUnavailable, Error::Get(e))),
+            },
+            None => Outcome::Error((
+                Status::InternalServerError,
+                Error::Get(panic_on_missing_state::<D>()),
+            )),
+        }
+    }
+}
+
+/// A request guard that retrieves a connection favoring `D`'s read
+/// replicas over its primary pool.
+///
+/// A replica is selected according to [`Config::replica_strategy`] from
+/// those configured via [`Config::replica_urls`]; an unreachable replica is
+/// temporarily demoted and skipped in favor of the others. If every replica
+/// is unreachable, or none are configured, this guard falls back to the
+/// primary pool, identically to [`Connection<D>`](Connection).
+pub struct ReadConnection<'r, D: Database> {
+    connection: Option<<D::Pool as Pool>::Connection>,
+    source: ReadSource<'r, D>,
+}
+
+enum ReadSource<'r, D: Database> {
+    Primary,
+    Replica { replicas: &'r Replicas<D::Pool>, index: usize },
+}
+
+/// The [`Replicas`] built for `D` by its [`Initializer`], kept around as
+/// managed state.
+///
+/// This is keyed by `D`, not `D::Pool`, so that two databases backed by the
+/// same driver pool type (e.g. two `sqlx::PgPool`-backed databases) don't
+/// collide in Rocket's managed state and overwrite one another's replicas.
+pub(crate) struct ReplicaSet<D: Database>(pub(crate) Replicas<D::Pool>, pub(crate) PhantomData<fn() -> D>);
+
+impl<'r, D: Database> Deref for ReadConnection<'r, D> {
+    type Target = <D::Pool as Pool>::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.connection.as_ref().expect("connection taken twice")
+    }
+}
+
+impl<'r, D: Database> DerefMut for ReadConnection<'r, D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.connection.as_mut().expect("connection taken twice")
+    }
+}
+
+impl<'r, D: Database> Drop for ReadConnection<'r, D> {
+    fn drop(&mut self) {
+        if let ReadSource::Replica { replicas, index } = &self.source {
+            replicas.release(*index);
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, D: Database> FromRequest<'r> for ReadConnection<'r, D> {
+    type Error = Error<<D::Pool as Pool>::Error>;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let db = match req.rocket().state::<D>() {
+            Some(db) => db,
+            None => return Outcome::Error((
+                Status::InternalServerError,
+                Error::Get(panic_on_missing_state::<D>()),
+            )),
+        };
+
+        // `Replicas::get()` already retries across every healthy replica;
+        // only once all of them have failed (or none are configured) do we
+        // draw from the primary, rather than surfacing a 503 for what could
+        // be a single flaky replica.
+        let from_replica = match req.rocket().state::<ReplicaSet<D>>() {
+            Some(ReplicaSet(replicas, _)) if !replicas.is_empty() => replicas.get().await.ok()
+                .map(|(index, conn)| (replicas, index, conn)),
+            _ => None,
+        };
+
+        if let Some((replicas, index, conn)) = from_replica {
+            return Outcome::Success(ReadConnection {
+                connection: Some(conn),
+                source: ReadSource::Replica { replicas, index },
+            });
+        }
+
+        match db.pool().get().await {
+            Ok(conn) => Outcome::Success(ReadConnection { connection: Some(conn), source: ReadSource::Primary }),
+            Err(e) => Outcome::Error((Status::ServiceUnavailable, Error::Get(e))),
+        }
+    }
+}
+
+fn panic_on_missing_state<D: Database>() -> ! {
+    panic!(
+        "database `{}` not attached: did you forget to call `.attach({}::init())`?",
+        D::NAME, std::any::type_name::<D>(),
+    )
+}
+
+/// A fairing that initializes a [`Database`]'s connection pool.
+///
+/// Created via [`Database::init()`]. Reads this database's [`Config`] from
+/// `databases.<name>`, initializes the pool, and makes `D` available as
+/// managed state. On shutdown, closes the pool via [`Pool::close()`].
+pub struct Initializer<D: Database>(PhantomData<fn() -> D>);
+
+impl<D: Database> Initializer<D> {
+    fn new() -> Self {
+        Initializer(PhantomData)
+    }
+}
+
+#[rocket::async_trait]
+impl<D: Database> Fairing for Initializer<D> {
+    fn info(&self) -> Info {
+        Info {
+            name: std::any::type_name::<D>(),
+            kind: Kind::Ignite | Kind::Shutdown,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        let workers: usize = rocket.figment()
+            .extract_inner(rocket::Config::WORKERS)
+            .unwrap_or_else(|_| rocket::Config::default().workers);
+
+        // Only `url` is required; fill in defaults for the other
+        // non-optional fields without overriding configured values.
+        let figment: Figment = rocket.figment()
+            .focus(&format!("databases.{}", D::NAME))
+            .join(Serialized::default("max_connections", workers * 4))
+            .join(Serialized::default("connect_timeout", 5));
+
+        let config: Config = match figment.extract() {
+            Ok(config) => config,
+            Err(e) => {
+                rocket::error!("database `{}` configuration error: {}", D::NAME, e);
+                return Err(rocket);
+            }
+        };
+
+        let pool = match <D::Pool as Pool>::init(&config).await {
+            Ok(pool) => pool,
+            Err(e) => {
+                rocket::error!("failed to initialize database `{}`: {}", D::NAME, e);
+                return Err(rocket);
+            }
+        };
+
+        let replicas = match Replicas::<D::Pool>::init(&config).await {
+            Ok(replicas) => replicas,
+            Err(e) => {
+                rocket::error!("failed to initialize replicas for database `{}`: {}", D::NAME, e);
+                return Err(rocket);
+            }
+        };
+
+        Ok(rocket
+            .manage(D::from(pool))
+            .manage(ReplicaSet::<D>(replicas, PhantomData))
+            .manage(StoredConfig::<D>(config, PhantomData)))
+    }
+
+    async fn on_shutdown(&self, rocket: &Rocket<Ignite>) {
+        if let Some(db) = rocket.state::<D>() {
+            db.pool().close().await;
+        }
+
+        if let Some(ReplicaSet(replicas, _)) = rocket.state::<ReplicaSet<D>>() {
+            replicas.close().await;
+        }
+    }
+}
+
+impl<D: Database> Sentinel for Connection<D> {
+    fn abort(rocket: &Rocket<Ignite>) -> bool {
+        rocket.state::<D>().is_none()
+    }
+}
+
+impl<'r, D: Database> Sentinel for ReadConnection<'r, D> {
+    fn abort(rocket: &Rocket<Ignite>) -> bool {
+        rocket.state::<D>().is_none()
+    }
+}
+
+/// The [`Config`] extracted for `D` by its [`Initializer`], kept around as
+/// managed state so a [`HealthMonitor`](crate::HealthMonitor) can read
+/// `health_check_interval` without re-extracting it from figment.
+pub(crate) struct StoredConfig<D: Database>(pub Config, pub(crate) PhantomData<fn() -> D>);
@@ -0,0 +1,34 @@
+use std::fmt;
+
+/// The error type returned by request guards backed by a [`Pool`](crate::Pool).
+///
+/// Pool initialization errors (from [`Pool::init()`](crate::Pool::init()))
+/// aren't represented here: they surface during
+/// [`Initializer::on_ignite()`](crate::Initializer), a [`Fairing`][fairing]
+/// that can only abort launch, not carry a payload, so they're logged via
+/// [`rocket::error!`] and launch is aborted directly.
+///
+/// [fairing]: rocket::fairing::Fairing
+#[derive(Debug)]
+pub enum Error<E> {
+    /// An error occurred while retrieving a connection from an initialized
+    /// pool, or while beginning, committing, or rolling back a
+    /// [`Transaction`](crate::Transaction) on one.
+    Get(E),
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Get(e) => write!(f, "error getting connection: {}", e),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for Error<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Get(e) => Some(e),
+        }
+    }
+}
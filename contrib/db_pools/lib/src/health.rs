@@ -0,0 +1,133 @@
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::serde::json::Json;
+use rocket::{get, routes, Build, Orbit, Rocket, State};
+use serde::Serialize;
+
+use crate::database::StoredConfig;
+use crate::pool::PoolStatus;
+use crate::Database;
+
+/// A database's health, as returned by [`Database::health()`] and by the
+/// endpoint mounted by [`HealthMonitor`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Health {
+    /// This database's configured name; see [`Database::NAME`].
+    pub name: &'static str,
+    /// Whether the most recent liveness check succeeded.
+    pub healthy: bool,
+    /// The pool's active/idle connection counts at the time of the check.
+    #[serde(flatten)]
+    pub status: PoolStatus,
+    /// Seconds since the last successful liveness check recorded by a
+    /// [`HealthMonitor`], if one is attached and has recorded one.
+    pub last_success_secs_ago: Option<u64>,
+}
+
+/// Tracks the last successful liveness check for `D`, kept as managed state.
+///
+/// Keyed by `D` rather than being a bare, ungeneric type so that two
+/// databases each attaching their own [`HealthMonitor`] don't collide in
+/// Rocket's managed state and share (overwrite) one another's timestamp.
+struct LastSuccess<D: Database>(Mutex<Option<Instant>>, PhantomData<fn() -> D>);
+
+/// A fairing that periodically checks a [`Database`]'s health and mounts a
+/// JSON endpoint reporting it.
+///
+/// Attach alongside `D::init()`, specifying the path to mount the endpoint
+/// at:
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "sqlx_sqlite")] mod _inner {
+/// # use rocket::launch;
+/// use rocket_db_pools::{sqlx, Database, HealthMonitor};
+///
+/// #[derive(Database)]
+/// #[database("sqlite_logs")]
+/// struct Logs(sqlx::SqlitePool);
+///
+/// #[launch]
+/// fn rocket() -> _ {
+///     rocket::build()
+///         .attach(Logs::init())
+///         .attach(HealthMonitor::<Logs>::fairing("/health/logs"))
+/// }
+/// # }
+/// ```
+///
+/// If `databases.<name>.health_check_interval` is set to a nonzero value, a
+/// background task
+/// periodically calls [`Database::health()`] and records the last success;
+/// regardless, the mounted endpoint always performs a fresh check when hit.
+pub struct HealthMonitor<D: Database> {
+    path: &'static str,
+    _database: PhantomData<fn() -> D>,
+}
+
+impl<D: Database> HealthMonitor<D> {
+    /// Returns a fairing that mounts a health endpoint for `D` at `path`.
+    pub fn fairing(path: &'static str) -> Self {
+        HealthMonitor { path, _database: PhantomData }
+    }
+}
+
+#[rocket::async_trait]
+impl<D: Database> Fairing for HealthMonitor<D> {
+    fn info(&self) -> Info {
+        Info {
+            name: "Database Health Monitor",
+            kind: Kind::Ignite | Kind::Liftoff,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        Ok(rocket
+            .manage(LastSuccess::<D>(Mutex::new(None), PhantomData))
+            .mount(self.path, routes![health::<D>]))
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        // A value of `0` disables background checks, matching the
+        // convention `Config::idle_timeout` documents for the same case.
+        let interval = rocket.state::<StoredConfig<D>>()
+            .and_then(|config| config.0.health_check_interval)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs);
+
+        let interval = match interval {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        let rocket = rocket.clone();
+        rocket::tokio::spawn(async move {
+            let mut ticker = rocket::tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let (db, last_success) = match (rocket.state::<D>(), rocket.state::<LastSuccess<D>>()) {
+                    (Some(db), Some(last_success)) => (db, last_success),
+                    _ => return,
+                };
+
+                if db.health().await.healthy {
+                    *last_success.0.lock().expect("last-success lock poisoned") = Some(Instant::now());
+                }
+            }
+        });
+    }
+}
+
+#[get("/")]
+async fn health<D: Database>(db: &State<D>, last_success: &State<LastSuccess<D>>) -> Json<Health> {
+    let mut health = db.health().await;
+    let mut recorded = last_success.0.lock().expect("last-success lock poisoned");
+    if health.healthy {
+        *recorded = Some(Instant::now());
+    }
+
+    health.last_success_secs_ago = recorded.map(|instant| instant.elapsed().as_secs());
+    Json(health)
+}
@@ -223,10 +223,63 @@
 //!   - sslmode                  : `PREFERRED`
 //!   - statement-cache-capacity : `100`
 //!
+//! # Transactions
+//!
+//! Use [`Transaction<D>`](Transaction) as a request guard to begin a
+//! transaction on retrieval. It dereferences to the underlying driver's
+//! transaction handle and is, by default, committed if the handler's
+//! response is `2xx`/`3xx` and rolled back otherwise. This requires
+//! attaching [`Transaction::<D>::fairing()`] in addition to `D::init()`, and
+//! is supported for drivers that implement [`TransactionalPool`] (`sqlx` and
+//! `deadpool_postgres`, at present). See [`Transaction`] for details and an
+//! example.
+//!
+//! # Read Replicas
+//!
+//! A database configuration may list one or more `replica_urls` in addition
+//! to its primary `url`. [`ReadConnection<D>`](ReadConnection) is a request
+//! guard, used exactly like [`Connection<D>`](Connection), that draws a
+//! connection from a replica pool instead of the primary, selected via
+//! [`Config::replica_strategy`] (round-robin by default). A replica that
+//! fails to yield a connection is temporarily skipped in favor of the
+//! others. If no replicas are configured, `ReadConnection<D>` behaves
+//! identically to `Connection<D>`. See [`Config`] for configuration details.
+//!
+//! # Health Checks
+//!
+//! [`Database::health()`] performs an on-demand liveness check ([`Pool::ping()`])
+//! of a database's pool and reports its active/idle connection counts
+//! ([`Pool::status()`]). To expose this over HTTP and periodically check it
+//! in the background, attach [`HealthMonitor::<D>::fairing()`](HealthMonitor::fairing)
+//! alongside `D::init()`, giving it a mount path:
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "sqlx_sqlite")] mod _inner {
+//! # use rocket::launch;
+//! use rocket_db_pools::{sqlx, Database, HealthMonitor};
+//!
+//! #[derive(Database)]
+//! #[database("sqlite_logs")]
+//! struct Logs(sqlx::SqlitePool);
+//!
+//! #[launch]
+//! fn rocket() -> _ {
+//!     rocket::build()
+//!         .attach(Logs::init())
+//!         .attach(HealthMonitor::<Logs>::fairing("/health/logs"))
+//! }
+//! # }
+//! ```
+//!
+//! Set `databases.db_name.health_check_interval` (seconds, nonzero) to additionally
+//! run the check periodically in the background; the endpoint itself always
+//! checks fresh on every request regardless.
+//!
 //! # Extending
 //!
 //! Any database driver can implement support for this library by implementing
-//! the [`Pool`] trait.
+//! the [`Pool`] trait, and [`TransactionalPool`] to additionally support
+//! [`Transaction`].
 
 #![doc(html_root_url = "https://api.rocket.rs/master/rocket_db_pools")]
 #![doc(html_favicon_url = "https://rocket.rs/images/favicon.ico")]
@@ -248,12 +301,16 @@ pub use rocket::figment;
 
 mod database;
 mod error;
+mod health;
 mod pool;
 mod config;
+mod transaction;
 
-pub use self::database::{Connection, Database, Initializer};
+pub use self::database::{Connection, Database, Initializer, ReadConnection};
 pub use self::error::Error;
-pub use self::pool::Pool;
+pub use self::health::{Health, HealthMonitor};
+pub use self::pool::{Pool, PoolStatus, TransactionalPool};
 pub use self::config::Config;
+pub use self::transaction::{Transaction, TransactionFairing};
 
 pub use rocket_db_pools_codegen::*;
@@ -0,0 +1,575 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rocket::async_trait;
+use serde::Serialize;
+
+use crate::config::ReplicaStrategy;
+use crate::Config;
+
+/// Trait implemented by all database pool types.
+///
+/// Types that implement this trait can be used as the inner connection pool
+/// type for a [`Database`](crate::Database). This trait is implemented for
+/// the pool types listed under [Provided
+/// Implementations](#provided-implementations) below; the other pool types
+/// in the [driver table](crate#supported-drivers) do not implement it.
+///
+/// # Provided Implementations
+///
+/// Implementations of `Pool` are provided for the following types:
+///
+/// | Driver             | `Pool` Type                  |
+/// |---------------------|------------------------------|
+/// | `deadpool_postgres`  | [`deadpool_postgres::Pool`]  |
+/// | `sqlx`               | [`sqlx::Pool<DB>`]           |
+///
+/// # Implementing `Pool`
+///
+/// A driver crate wishing to support `rocket_db_pools` implements this trait
+/// for some pool type it exports, using [`Config`] to initialize the pool
+/// from Rocket's configuration.
+#[async_trait]
+pub trait Pool: Sized + Send + Sync + 'static {
+    /// The error type returned by [`Self::init()`] and [`Self::get()`].
+    type Error: std::error::Error;
+
+    /// The connection type managed by this pool, returned by [`Self::get()`].
+    type Connection: Send;
+
+    /// Constructs a pool from [`Config`].
+    async fn init(config: &Config) -> Result<Self, Self::Error>;
+
+    /// Asynchronously retrieves a connection from the pool.
+    async fn get(&self) -> Result<Self::Connection, Self::Error>;
+
+    /// Ends use of this pool, permitting it to release held resources.
+    ///
+    /// The default implementation does nothing.
+    async fn close(&self) {}
+
+    /// Checks that the pool can currently serve connections by running a
+    /// lightweight, driver-specific liveness query against one.
+    ///
+    /// The default implementation merely acquires and drops a connection;
+    /// drivers should override this to run an actual query.
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.get().await.map(drop)
+    }
+
+    /// Returns a snapshot of this pool's current active/idle connection
+    /// counts.
+    ///
+    /// The default implementation reports zero for both; drivers that
+    /// expose this information should override it.
+    fn status(&self) -> PoolStatus {
+        PoolStatus::default()
+    }
+}
+
+/// A snapshot of a pool's connection counts, as returned by [`Pool::status()`].
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PoolStatus {
+    /// Number of connections currently checked out of the pool.
+    pub active: u32,
+    /// Number of idle connections currently held by the pool.
+    pub idle: u32,
+}
+
+/// Trait implemented by [`Pool`]s that also support explicit transactions.
+///
+/// A driver implements this trait, in addition to [`Pool`], to enable the
+/// [`Transaction`](crate::Transaction) request guard for its connection
+/// type. Implementations are provided for `sqlx` and `deadpool_postgres`
+/// pools behind their respective feature flags; `diesel` pools do not
+/// implement it.
+#[async_trait]
+pub trait TransactionalPool: Pool {
+    /// A handle to an in-progress transaction, dereferenced to by
+    /// [`Transaction`](crate::Transaction).
+    type Transaction: Send;
+
+    /// Begins a transaction on `connection`, returning a handle to it.
+    async fn begin(
+        connection: Self::Connection,
+    ) -> Result<Self::Transaction, Self::Error>;
+
+    /// Commits a previously-begun transaction.
+    async fn commit(transaction: Self::Transaction) -> Result<(), Self::Error>;
+
+    /// Rolls back a previously-begun transaction.
+    async fn rollback(transaction: Self::Transaction) -> Result<(), Self::Error>;
+}
+
+/// How long a replica that failed to yield a connection is skipped for
+/// before [`Replicas`] retries it.
+const DEMOTION_BACKOFF: Duration = Duration::from_secs(30);
+
+struct ReplicaEntry<P> {
+    pool: P,
+    in_flight: AtomicUsize,
+    demoted_until: Mutex<Option<Instant>>,
+}
+
+/// A set of read-replica pools selected from according to a
+/// [`ReplicaStrategy`].
+///
+/// Built by [`Initializer`](crate::Initializer) from
+/// [`Config::replica_urls`] and drawn from by
+/// [`ReadConnection<D>`](crate::ReadConnection). A replica that fails to
+/// yield a connection is temporarily demoted and [`Replicas::get()`] falls
+/// through to the next healthy one instead of failing the request outright;
+/// if every replica is currently demoted, selection fails open rather than
+/// rejecting reads outright.
+pub struct Replicas<P: Pool> {
+    entries: Vec<ReplicaEntry<P>>,
+    strategy: ReplicaStrategy,
+    next: AtomicUsize,
+}
+
+impl<P: Pool> Replicas<P> {
+    /// Builds a replica pool for each of `config.replica_urls`.
+    pub(crate) async fn init(config: &Config) -> Result<Self, P::Error> {
+        let mut entries = Vec::with_capacity(config.replica_urls.len());
+        for url in &config.replica_urls {
+            let replica_config = Config { url: url.clone(), ..config.clone() };
+            let pool = P::init(&replica_config).await?;
+            entries.push(ReplicaEntry {
+                pool,
+                in_flight: AtomicUsize::new(0),
+                demoted_until: Mutex::new(None),
+            });
+        }
+
+        Ok(Replicas { entries, strategy: config.replica_strategy, next: AtomicUsize::new(0) })
+    }
+
+    /// Returns `true` if no replicas are configured.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn healthy_indices(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let healthy: Vec<usize> = self.entries.iter().enumerate()
+            .filter(|(_, e)| {
+                e.demoted_until.lock().expect("demotion lock poisoned")
+                    .map_or(true, |until| now >= until)
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        if healthy.is_empty() {
+            (0..self.entries.len()).collect()
+        } else {
+            healthy
+        }
+    }
+
+    /// Returns healthy replica indices in the order [`Replicas::get()`]
+    /// should try them: the preferred replica per [`ReplicaStrategy`]
+    /// first, then the rest, so a failure can fall through to another
+    /// healthy replica instead of failing the request outright.
+    fn candidates(&self) -> Vec<usize> {
+        let healthy = self.healthy_indices();
+        match self.strategy {
+            ReplicaStrategy::RoundRobin => {
+                let i = self.next.fetch_add(1, Ordering::Relaxed) % healthy.len();
+                let mut ordered = healthy[i..].to_vec();
+                ordered.extend_from_slice(&healthy[..i]);
+                ordered
+            }
+            ReplicaStrategy::LeastConnections => {
+                let mut ordered = healthy;
+                ordered.sort_by_key(|&i| self.entries[i].in_flight.load(Ordering::Relaxed));
+                ordered
+            }
+        }
+    }
+
+    /// Acquires a connection from a healthy replica, returning its index
+    /// alongside it so callers can report when they're done with it via
+    /// [`Replicas::release()`].
+    ///
+    /// Tries candidates in [`Replicas::candidates()`] order, demoting and
+    /// skipping past any that fail to yield a connection, so one flaky
+    /// replica doesn't fail the request while others (or a demoted-but-now-
+    /// recovered one) could have served it. Fails only once every replica
+    /// has been tried and failed.
+    pub(crate) async fn get(&self) -> Result<(usize, P::Connection), P::Error> {
+        let mut last_err = None;
+        for index in self.candidates() {
+            let entry = &self.entries[index];
+            match entry.pool.get().await {
+                Ok(conn) => {
+                    entry.in_flight.fetch_add(1, Ordering::Relaxed);
+                    return Ok((index, conn));
+                }
+                Err(e) => {
+                    *entry.demoted_until.lock().expect("demotion lock poisoned") =
+                        Some(Instant::now() + DEMOTION_BACKOFF);
+                    last_err = Some(e);
+                }
+            }
+        }
+
+        Err(last_err.expect("Replicas::get() only called when not Replicas::is_empty()"))
+    }
+
+    /// Records that a connection previously returned by [`Replicas::get()`]
+    /// for replica `index` is no longer in use.
+    pub(crate) fn release(&self, index: usize) {
+        self.entries[index].in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) async fn close(&self) {
+        for entry in &self.entries {
+            entry.pool.close().await;
+        }
+    }
+}
+
+/// Extends a mutable reference into a just-boxed `T` to `'static`.
+///
+/// Used to hand out a reference into a heap-allocated value whose address
+/// is stable even though the `Box` isn't `'static` itself — e.g. building a
+/// self-referential struct that keeps the `Box` and a borrow into it
+/// together, like [`deadpool_postgres_impl::OwnedTransaction`].
+///
+/// # Safety
+///
+/// The caller must ensure the returned reference does not outlive `boxed`,
+/// and that the boxed value is not moved out of the box, reallocated, or
+/// otherwise accessed while the returned reference is live. Moving the `Box`
+/// itself leaves the value in place. In practice this means: store both
+/// together, and drop the returned reference (or anything derived from it)
+/// before dropping `boxed`.
+#[allow(dead_code)] // only used by driver impls behind feature flags
+unsafe fn extend_mut_to_static<T>(boxed: &mut Box<T>) -> &'static mut T {
+    &mut *(boxed.as_mut() as *mut T)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct DummyPool;
+
+    #[async_trait]
+    impl Pool for DummyPool {
+        type Error = std::convert::Infallible;
+        type Connection = ();
+
+        async fn init(_config: &Config) -> Result<Self, Self::Error> {
+            Ok(DummyPool)
+        }
+
+        async fn get(&self) -> Result<Self::Connection, Self::Error> {
+            Ok(())
+        }
+    }
+
+    fn replicas(count: usize, strategy: ReplicaStrategy) -> Replicas<DummyPool> {
+        let entries = (0..count)
+            .map(|_| ReplicaEntry {
+                pool: DummyPool,
+                in_flight: AtomicUsize::new(0),
+                demoted_until: Mutex::new(None),
+            })
+            .collect();
+
+        Replicas { entries, strategy, next: AtomicUsize::new(0) }
+    }
+
+    #[test]
+    fn round_robin_cycles_through_all_replicas() {
+        let r = replicas(3, ReplicaStrategy::RoundRobin);
+        let preferred: Vec<usize> = (0..6).map(|_| r.candidates()[0]).collect();
+        assert_eq!(preferred, vec![0, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn round_robin_candidates_include_the_rest_as_fallback() {
+        let r = replicas(3, ReplicaStrategy::RoundRobin);
+        assert_eq!(r.candidates(), vec![0, 1, 2]);
+        assert_eq!(r.candidates(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn least_connections_orders_by_fewest_in_flight() {
+        let r = replicas(3, ReplicaStrategy::LeastConnections);
+        r.entries[0].in_flight.store(5, Ordering::Relaxed);
+        r.entries[1].in_flight.store(1, Ordering::Relaxed);
+        r.entries[2].in_flight.store(3, Ordering::Relaxed);
+
+        assert_eq!(r.candidates(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn healthy_indices_excludes_demoted_replicas() {
+        let r = replicas(3, ReplicaStrategy::RoundRobin);
+        *r.entries[1].demoted_until.lock().unwrap() = Some(Instant::now() + DEMOTION_BACKOFF);
+
+        assert_eq!(r.healthy_indices(), vec![0, 2]);
+    }
+
+    #[test]
+    fn healthy_indices_fails_open_when_all_demoted() {
+        let r = replicas(2, ReplicaStrategy::RoundRobin);
+        for entry in &r.entries {
+            *entry.demoted_until.lock().unwrap() = Some(Instant::now() + DEMOTION_BACKOFF);
+        }
+
+        assert_eq!(r.healthy_indices(), vec![0, 1]);
+    }
+
+    #[test]
+    fn healthy_indices_includes_replicas_past_their_backoff() {
+        let r = replicas(2, ReplicaStrategy::RoundRobin);
+        *r.entries[0].demoted_until.lock().unwrap() = Some(Instant::now() - Duration::from_secs(1));
+
+        assert_eq!(r.healthy_indices(), vec![0, 1]);
+    }
+
+    struct FlakyPool(std::sync::atomic::AtomicBool);
+
+    #[async_trait]
+    impl Pool for FlakyPool {
+        type Error = std::io::Error;
+        type Connection = ();
+
+        async fn init(_config: &Config) -> Result<Self, Self::Error> {
+            Ok(FlakyPool(std::sync::atomic::AtomicBool::new(false)))
+        }
+
+        async fn get(&self) -> Result<Self::Connection, Self::Error> {
+            if self.0.load(Ordering::Relaxed) {
+                Err(std::io::Error::new(std::io::ErrorKind::Other, "flaky"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn flaky_replicas(count: usize, strategy: ReplicaStrategy) -> Replicas<FlakyPool> {
+        let entries = (0..count)
+            .map(|_| ReplicaEntry {
+                pool: FlakyPool(std::sync::atomic::AtomicBool::new(false)),
+                in_flight: AtomicUsize::new(0),
+                demoted_until: Mutex::new(None),
+            })
+            .collect();
+
+        Replicas { entries, strategy, next: AtomicUsize::new(0) }
+    }
+
+    #[rocket::async_test]
+    async fn get_falls_through_to_the_next_healthy_replica_on_failure() {
+        let r = flaky_replicas(3, ReplicaStrategy::RoundRobin);
+        r.entries[0].pool.0.store(true, Ordering::Relaxed);
+
+        let (index, _) = r.get().await.expect("replica 1 or 2 should serve the request");
+        assert_ne!(index, 0);
+    }
+
+    #[rocket::async_test]
+    async fn get_demotes_each_replica_it_falls_through() {
+        let r = flaky_replicas(3, ReplicaStrategy::RoundRobin);
+        r.entries[0].pool.0.store(true, Ordering::Relaxed);
+
+        r.get().await.expect("replica 1 or 2 should serve the request");
+        assert!(r.entries[0].demoted_until.lock().unwrap().is_some());
+    }
+
+    #[rocket::async_test]
+    async fn get_fails_only_once_every_replica_has_failed() {
+        let r = flaky_replicas(2, ReplicaStrategy::RoundRobin);
+        for entry in &r.entries {
+            entry.pool.0.store(true, Ordering::Relaxed);
+        }
+
+        assert!(r.get().await.is_err());
+    }
+
+    #[test]
+    fn extend_mut_to_static_reference_reads_and_writes_through_the_box() {
+        let mut boxed = Box::new(String::from("hello"));
+        {
+            // SAFETY: `extended` is dropped (end of this block) well before
+            // `boxed` is dropped at the end of the test, and `boxed` isn't
+            // touched while `extended` is live.
+            let extended: &'static mut String = unsafe { extend_mut_to_static(&mut boxed) };
+            extended.push_str(", world");
+        }
+
+        assert_eq!(*boxed, "hello, world");
+    }
+}
+
+#[cfg(feature = "sqlx")]
+mod sqlx_impl {
+    use super::*;
+    use sqlx::{pool::PoolConnection, Database as SqlxDatabase, Pool as SqlxPool, Transaction as SqlxTransaction};
+
+    #[async_trait]
+    impl<DB: SqlxDatabase> Pool for SqlxPool<DB> {
+        type Error = sqlx::Error;
+        type Connection = PoolConnection<DB>;
+
+        async fn init(config: &Config) -> Result<Self, Self::Error> {
+            sqlx::pool::PoolOptions::new()
+                .min_connections(config.min_connections.unwrap_or_default())
+                .max_connections(config.max_connections as u32)
+                .acquire_timeout(Duration::from_secs(config.connect_timeout))
+                .idle_timeout(config.idle_timeout.filter(|secs| *secs > 0).map(Duration::from_secs))
+                .connect(&config.url)
+                .await
+        }
+
+        async fn get(&self) -> Result<Self::Connection, Self::Error> {
+            self.acquire().await
+        }
+
+        async fn close(&self) {
+            <sqlx::Pool<DB>>::close(self).await;
+        }
+
+        async fn ping(&self) -> Result<(), Self::Error> {
+            sqlx::query("SELECT 1").execute(self).await.map(drop)
+        }
+
+        fn status(&self) -> PoolStatus {
+            PoolStatus { active: self.size().saturating_sub(self.num_idle() as u32), idle: self.num_idle() as u32 }
+        }
+    }
+
+    #[async_trait]
+    impl<DB: SqlxDatabase> TransactionalPool for SqlxPool<DB> {
+        type Transaction = SqlxTransaction<'static, DB>;
+
+        async fn begin(connection: Self::Connection) -> Result<Self::Transaction, Self::Error> {
+            connection.begin().await
+        }
+
+        async fn commit(transaction: Self::Transaction) -> Result<(), Self::Error> {
+            transaction.commit().await
+        }
+
+        async fn rollback(transaction: Self::Transaction) -> Result<(), Self::Error> {
+            transaction.rollback().await
+        }
+    }
+}
+
+#[cfg(feature = "deadpool_postgres")]
+mod deadpool_postgres_impl {
+    use super::*;
+    use deadpool_postgres::Pool as DeadpoolPool;
+
+    #[async_trait]
+    impl Pool for DeadpoolPool {
+        type Error = deadpool_postgres::PoolError;
+        type Connection = deadpool_postgres::Client;
+
+        async fn init(config: &Config) -> Result<Self, Self::Error> {
+            let pg_config = config.url.parse::<tokio_postgres::Config>()
+                .map_err(|e| deadpool_postgres::PoolError::Backend(e.into()))?;
+            let manager = deadpool_postgres::Manager::new(pg_config, tokio_postgres::NoTls);
+            deadpool_postgres::Pool::builder(manager)
+                .max_size(config.max_connections)
+                .build()
+                .map_err(|e| deadpool_postgres::PoolError::Backend(e.into()))
+        }
+
+        async fn get(&self) -> Result<Self::Connection, Self::Error> {
+            self.get().await
+        }
+
+        async fn close(&self) {
+            <DeadpoolPool>::close(self);
+        }
+
+        async fn ping(&self) -> Result<(), Self::Error> {
+            let client = self.get().await?;
+            client.simple_query("SELECT 1").await
+                .map(drop)
+                .map_err(deadpool_postgres::PoolError::Backend)
+        }
+
+        fn status(&self) -> PoolStatus {
+            let status = deadpool_postgres::Pool::status(self);
+            let idle = status.available.max(0) as u32;
+            PoolStatus { active: (status.size as u32).saturating_sub(idle), idle }
+        }
+    }
+
+    /// A transaction borrowed from an owned, boxed [`deadpool_postgres::Client`].
+    ///
+    /// `deadpool_postgres::Transaction<'a>` borrows its client, which is
+    /// incompatible with a connection pool's owned, 'static connections. We
+    /// box the client so its address is stable, hand out a transaction
+    /// borrowing through the box, and keep both alive together here. The
+    /// transaction is always dropped before the client it borrows from.
+    pub struct OwnedTransaction {
+        transaction: Option<deadpool_postgres::Transaction<'static>>,
+        // Kept alive for as long as `transaction`; never accessed directly.
+        _client: Box<deadpool_postgres::Client>,
+    }
+
+    impl std::ops::Deref for OwnedTransaction {
+        type Target = deadpool_postgres::Transaction<'static>;
+
+        fn deref(&self) -> &Self::Target {
+            self.transaction.as_ref().expect("transaction present until drop")
+        }
+    }
+
+    impl std::ops::DerefMut for OwnedTransaction {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.transaction.as_mut().expect("transaction present until drop")
+        }
+    }
+
+    impl Drop for OwnedTransaction {
+        fn drop(&mut self) {
+            // Drop the borrowing transaction before the client it points into.
+            self.transaction.take();
+        }
+    }
+
+    #[async_trait]
+    impl TransactionalPool for DeadpoolPool {
+        type Transaction = OwnedTransaction;
+
+        async fn begin(connection: Self::Connection) -> Result<Self::Transaction, Self::Error> {
+            let mut client = Box::new(connection);
+            // SAFETY: the boxed client is not moved out of its box,
+            // reallocated, or otherwise accessed again; only the `Box` is
+            // moved, into `OwnedTransaction`, alongside the resulting
+            // transaction, and `OwnedTransaction` always drops the
+            // transaction before the client (see its `Drop` impl).
+            let client_ref: &'static mut deadpool_postgres::Client =
+                unsafe { extend_mut_to_static(&mut client) };
+
+            let transaction = client_ref.transaction().await
+                .map_err(deadpool_postgres::PoolError::Backend)?;
+
+            Ok(OwnedTransaction { transaction: Some(transaction), _client: client })
+        }
+
+        async fn commit(mut transaction: Self::Transaction) -> Result<(), Self::Error> {
+            transaction.transaction.take()
+                .expect("transaction present until drop")
+                .commit().await
+                .map_err(deadpool_postgres::PoolError::Backend)
+        }
+
+        async fn rollback(mut transaction: Self::Transaction) -> Result<(), Self::Error> {
+            transaction.transaction.take()
+                .expect("transaction present until drop")
+                .rollback().await
+                .map_err(deadpool_postgres::PoolError::Backend)
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{Ignite, Response, Rocket, Sentinel};
+
+use crate::{Database, Error, Pool, TransactionalPool};
+
+type Pending<D> = Mutex<Option<<<D as Database>::Pool as TransactionalPool>::Transaction>>;
+
+/// A request guard that begins a transaction on retrieval and automatically
+/// commits or rolls it back once the response has been finalized.
+///
+/// On success, a connection is drawn from `D`'s pool and a transaction is
+/// begun on it via [`TransactionalPool::begin()`]. The guard dereferences to
+/// the driver's transaction handle, so it can be used exactly like a
+/// [`Connection`](crate::Connection) in queries.
+///
+/// Once the handler returns and a final response status is known, the
+/// transaction is **committed** if the status is `2xx` or `3xx`, and
+/// **rolled back** otherwise. This requires [`Transaction::fairing()`] to be
+/// attached in addition to `D::init()`:
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "sqlx_sqlite")] mod _inner {
+/// # use rocket::launch;
+/// use rocket_db_pools::{sqlx, Database, Transaction};
+///
+/// #[derive(Database)]
+/// #[database("sqlite_logs")]
+/// struct Logs(sqlx::SqlitePool);
+///
+/// #[launch]
+/// fn rocket() -> _ {
+///     rocket::build()
+///         .attach(Logs::init())
+///         .attach(Transaction::<Logs>::fairing())
+/// }
+/// # }
+/// ```
+///
+/// To opt out of this automatic behavior for a particular request, consume
+/// the guard with [`Transaction::commit()`] or [`Transaction::rollback()`]
+/// explicitly; doing so finalizes the transaction immediately and the
+/// fairing takes no further action on it.
+pub struct Transaction<'r, D: Database>
+where
+    D::Pool: TransactionalPool,
+{
+    transaction: Option<<D::Pool as TransactionalPool>::Transaction>,
+    pending: &'r Pending<D>,
+}
+
+impl<'r, D: Database> Transaction<'r, D>
+where
+    D::Pool: TransactionalPool,
+{
+    /// Commits this transaction immediately, bypassing the automatic,
+    /// response-status-driven behavior of [`Transaction::fairing()`].
+    pub async fn commit(mut self) -> Result<(), Error<<D::Pool as Pool>::Error>> {
+        let transaction = self.transaction.take().expect("transaction taken twice");
+        <D::Pool as TransactionalPool>::commit(transaction).await.map_err(Error::Get)
+    }
+
+    /// Rolls back this transaction immediately, bypassing the automatic,
+    /// response-status-driven behavior of [`Transaction::fairing()`].
+    pub async fn rollback(mut self) -> Result<(), Error<<D::Pool as Pool>::Error>> {
+        let transaction = self.transaction.take().expect("transaction taken twice");
+        <D::Pool as TransactionalPool>::rollback(transaction).await.map_err(Error::Get)
+    }
+
+    /// Returns the fairing that finalizes transactions begun via this guard.
+    ///
+    /// Must be attached alongside `D::init()` for automatic commit/rollback
+    /// to take effect.
+    pub fn fairing() -> TransactionFairing<D> {
+        TransactionFairing(PhantomData)
+    }
+}
+
+impl<'r, D: Database> Deref for Transaction<'r, D>
+where
+    D::Pool: TransactionalPool,
+{
+    type Target = <D::Pool as TransactionalPool>::Transaction;
+
+    fn deref(&self) -> &Self::Target {
+        self.transaction.as_ref().expect("transaction taken twice")
+    }
+}
+
+impl<'r, D: Database> DerefMut for Transaction<'r, D>
+where
+    D::Pool: TransactionalPool,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.transaction.as_mut().expect("transaction taken twice")
+    }
+}
+
+/// Hands an unfinalized transaction off to [`TransactionFairing`] for
+/// commit/rollback once the response is known. A transaction finalized
+/// explicitly via [`Transaction::commit()`] or [`Transaction::rollback()`]
+/// has already been taken by the time this runs, so there's nothing to do.
+impl<'r, D: Database> Drop for Transaction<'r, D>
+where
+    D::Pool: TransactionalPool,
+{
+    fn drop(&mut self) {
+        if let Some(transaction) = self.transaction.take() {
+            *self.pending.lock().expect("pending transaction lock poisoned") = Some(transaction);
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, D: Database> FromRequest<'r> for Transaction<'r, D>
+where
+    D::Pool: TransactionalPool,
+{
+    type Error = Error<<D::Pool as Pool>::Error>;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let db = match req.rocket().state::<D>() {
+            Some(db) => db,
+            None => {
+                rocket::error!(
+                    "database `{}` not attached: did you forget to call `.attach({}::init())`?",
+                    D::NAME, std::any::type_name::<D>(),
+                );
+                return Outcome::Forward(Status::InternalServerError);
+            }
+        };
+
+        let connection = match db.pool().get().await {
+            Ok(connection) => connection,
+            Err(e) => return Outcome::Error((Status::ServiceUnavailable, Error::Get(e))),
+        };
+
+        match <D::Pool as TransactionalPool>::begin(connection).await {
+            Ok(transaction) => {
+                let pending = req.local_cache(|| Pending::<D>::new(None));
+                Outcome::Success(Transaction { transaction: Some(transaction), pending })
+            }
+            Err(e) => Outcome::Error((Status::ServiceUnavailable, Error::Get(e))),
+        }
+    }
+}
+
+impl<'r, D: Database> Sentinel for Transaction<'r, D>
+where
+    D::Pool: TransactionalPool,
+{
+    fn abort(rocket: &Rocket<Ignite>) -> bool {
+        rocket.state::<D>().is_none()
+    }
+}
+
+/// A response [`Fairing`] that commits or rolls back transactions begun by
+/// [`Transaction<D>`] guards, based on the final response status.
+///
+/// See [`Transaction`] for usage. Returned by [`Transaction::fairing()`].
+pub struct TransactionFairing<D: Database>(PhantomData<fn() -> D>)
+where
+    D::Pool: TransactionalPool;
+
+#[rocket::async_trait]
+impl<D: Database> Fairing for TransactionFairing<D>
+where
+    D::Pool: TransactionalPool,
+{
+    fn info(&self) -> Info {
+        Info {
+            name: "Database Transaction Finalizer",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let pending = req.local_cache(|| Pending::<D>::new(None));
+        let transaction = match pending.lock().expect("pending transaction lock poisoned").take() {
+            Some(transaction) => transaction,
+            None => return,
+        };
+
+        let result = if should_commit(res.status()) {
+            <D::Pool as TransactionalPool>::commit(transaction).await
+        } else {
+            <D::Pool as TransactionalPool>::rollback(transaction).await
+        };
+
+        if let Err(e) = result {
+            rocket::error!("failed to finalize transaction for `{}`: {}", D::NAME, e);
+        }
+    }
+}
+
+/// Whether a transaction finalized by [`TransactionFairing`] should be
+/// committed (`true`) or rolled back (`false`) given the response's final
+/// status: `2xx` and `3xx` commit, everything else rolls back.
+fn should_commit(status: Status) -> bool {
+    let class = status.class();
+    class.is_success() || class.is_redirection()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commits_on_success_and_redirection() {
+        assert!(should_commit(Status::Ok));
+        assert!(should_commit(Status::Created));
+        assert!(should_commit(Status::NoContent));
+        assert!(should_commit(Status::MovedPermanently));
+        assert!(should_commit(Status::NotModified));
+    }
+
+    #[test]
+    fn rolls_back_on_client_and_server_errors() {
+        assert!(!should_commit(Status::BadRequest));
+        assert!(!should_commit(Status::NotFound));
+        assert!(!should_commit(Status::InternalServerError));
+        assert!(!should_commit(Status::ServiceUnavailable));
+    }
+}
@@ -5,13 +5,176 @@
 //! guide](https://rocket.rs/master/guide/configuration/#tls). See
 //! [`Certificate`] for a request guard that validated, verifies, and retrieves
 //! client certificates.
+//!
+//! # Revocation
+//!
+//! In addition to chain verification, the [`Certificate`] guard can check a
+//! presented certificate against one or more Certificate Revocation Lists
+//! (CRLs). List one or more DER or PEM-encoded CRL files under
+//! `tls.mutual.revocation_lists` in your configuration:
+//!
+//! ```toml
+//! [default.tls.mutual]
+//! ca_certs = "ca_cert.pem"
+//! revocation_lists = ["ca.crl"]
+//! ```
+//!
+//! Attach [`mtls::fairing()`](fairing()) to parse configured CRLs once, at
+//! ignite, and cache them as managed state for the lifetime of that `Rocket`
+//! instance:
+//!
+//! ```rust,no_run
+//! # use rocket::launch;
+//! #[launch]
+//! fn rocket() -> _ {
+//!     rocket::build().attach(rocket::mtls::fairing())
+//! }
+//! ```
+//!
+//! Without this fairing attached, revocation checking still works, but CRLs
+//! are re-parsed on every request instead of cached, and a CRL file that
+//! fails to read or parse fails that request with [`Error::Configuration`]
+//! rather than being silently ignored. With the fairing attached, the same
+//! failure is fatal at ignite, aborting launch instead.
+//!
+//! A certificate whose serial number appears on a CRL issued by its issuer
+//! is rejected with [`Error::Revoked`], distinguishable from chain
+//! parse/verify failures ([`Error::Verify`]).
 
 #[doc(inline)]
 pub use crate::http::tls::mtls::*;
 
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::fairing::{AdHoc, Fairing};
+use crate::http::tls::mtls::Error as VerifyError;
 use crate::http::Status;
 use crate::outcome::{try_outcome, IntoOutcome};
 use crate::request::{FromRequest, Outcome, Request};
+use crate::{Build, Rocket};
+
+/// Error returned by the [`Certificate`] request guard.
+#[derive(Debug)]
+pub enum Error {
+    /// The certificate chain failed to parse or verify.
+    Verify(VerifyError),
+    /// The certificate's serial number was found on a CRL issued by its
+    /// issuer, configured via `tls.mutual.revocation_lists`.
+    Revoked,
+    /// A CRL configured via `tls.mutual.revocation_lists` failed to read or
+    /// parse. Only returned when [`fairing()`] isn't attached; with it
+    /// attached, the same failure aborts launch instead. See the server log
+    /// for which file and why.
+    Configuration,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Verify(e) => write!(f, "certificate verification failed: {}", e),
+            Error::Revoked => write!(f, "certificate has been revoked"),
+            Error::Configuration => write!(f, "certificate revocation list configuration is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Verify(e) => Some(e),
+            Error::Revoked | Error::Configuration => None,
+        }
+    }
+}
+
+/// Revocation checking configuration, read from `tls.mutual.revocation_lists`.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Revocation {
+    #[serde(default)]
+    revocation_lists: Vec<PathBuf>,
+}
+
+struct Crl {
+    issuer: Vec<u8>,
+    revoked_serials: Vec<Vec<u8>>,
+}
+
+/// Parsed CRLs for this `Rocket` instance's configured
+/// `tls.mutual.revocation_lists`, computed once at ignite by [`fairing()`]
+/// and cached as managed state.
+///
+/// Scoped to the owning `Rocket` instance rather than a process-wide global
+/// so that two instances built in the same process (routine in this crate's
+/// own test harness) don't share CRL data, even if their configurations
+/// differ.
+struct Crls(Vec<Crl>);
+
+/// Parses every path in `tls.mutual.revocation_lists`, failing on the first
+/// one that can't be read or parsed as a CRL rather than silently skipping
+/// it — an operator who mistypes a path or ships a corrupt CRL file should
+/// find out, not keep accepting possibly-revoked certificates.
+fn parse_configured_crls(rocket: &Rocket<Build>) -> Result<Vec<Crl>, ()> {
+    let revocation: Revocation = rocket.figment()
+        .extract_inner("tls.mutual")
+        .unwrap_or_default();
+
+    revocation.revocation_lists.iter().map(|path| parse_crl(path)).collect()
+}
+
+/// Returns a fairing that parses `tls.mutual.revocation_lists` at ignite and
+/// caches the result as managed state, so the [`Certificate`] guard's
+/// revocation check doesn't re-parse the configured CRL files on every
+/// request.
+///
+/// Attaching this is optional; see the [module docs](self) for the tradeoff.
+/// If a configured CRL file fails to read or parse, launch is aborted; see
+/// the server log for which file and why.
+pub fn fairing() -> impl Fairing {
+    AdHoc::try_on_ignite("Certificate Revocation Lists", |rocket| async {
+        match parse_configured_crls(&rocket) {
+            Ok(crls) => Ok(rocket.manage(Crls(crls))),
+            Err(()) => Err(rocket),
+        }
+    })
+}
+
+/// Strips PEM armor from `bytes` if present, returning DER bytes either way.
+fn to_der(bytes: &[u8]) -> Vec<u8> {
+    if bytes.starts_with(b"-----BEGIN") {
+        x509_parser::pem::Pem::iter_from_buffer(bytes)
+            .filter_map(Result::ok)
+            .next()
+            .map(|pem| pem.contents)
+            .unwrap_or_default()
+    } else {
+        bytes.to_vec()
+    }
+}
+
+fn parse_crl(path: &std::path::Path) -> Result<Crl, ()> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| rocket::error!("failed to read CRL file `{}`: {}", path.display(), e))?;
+
+    let der = to_der(&bytes);
+    let (_, crl) = x509_parser::revocation_list::CertificateRevocationList::from_der(&der)
+        .map_err(|e| rocket::error!("failed to parse CRL file `{}`: {}", path.display(), e))?;
+
+    let issuer = crl.tbs_cert_list.issuer.as_raw().to_vec();
+    let revoked_serials = crl.tbs_cert_list.revoked_certificates.iter()
+        .map(|revoked| revoked.user_certificate.to_bytes_be())
+        .collect();
+
+    Ok(Crl { issuer, revoked_serials })
+}
+
+fn is_revoked(issuer: &[u8], serial: &[u8], crls: &[Crl]) -> bool {
+    crls.iter()
+        .filter(|crl| crl.issuer == issuer)
+        .any(|crl| crl.revoked_serials.iter().any(|revoked| revoked == serial))
+}
 
 #[crate::async_trait]
 impl<'r> FromRequest<'r> for Certificate<'r> {
@@ -26,6 +189,81 @@ impl<'r> FromRequest<'r> for Certificate<'r> {
         let data = try_outcome!(try_outcome!(certs)
             .chain_data()
             .or_forward(Status::Unauthorized));
-        Certificate::parse(data).or_error(Status::Unauthorized)
+        let certificate = match Certificate::parse(data) {
+            Ok(certificate) => certificate,
+            Err(e) => return Outcome::Error((Status::Unauthorized, Error::Verify(e))),
+        };
+
+        let revocation: Revocation = req.rocket().figment()
+            .extract_inner("tls.mutual")
+            .unwrap_or_default();
+
+        if !revocation.revocation_lists.is_empty() {
+            let crls = match req.rocket().state::<Crls>() {
+                Some(crls) => Cow::Borrowed(&crls.0),
+                None => {
+                    let parsed: Result<Vec<Crl>, ()> = revocation.revocation_lists.iter()
+                        .map(|path| parse_crl(path))
+                        .collect();
+
+                    match parsed {
+                        Ok(crls) => Cow::Owned(crls),
+                        Err(()) => return Outcome::Error((Status::InternalServerError, Error::Configuration)),
+                    }
+                }
+            };
+
+            let issuer = certificate.tbs_certificate.issuer.as_raw();
+            let serial = certificate.tbs_certificate.serial.to_bytes_be();
+            if is_revoked(issuer, &serial, &crls) {
+                return Outcome::Error((Status::Unauthorized, Error::Revoked));
+            }
+        }
+
+        Outcome::Success(certificate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_der_passes_through_der_unchanged() {
+        let der = [0x30, 0x03, 0x02, 0x01, 0x05];
+        assert_eq!(to_der(&der), der);
+    }
+
+    #[test]
+    fn to_der_strips_pem_armor() {
+        // Base64 of `[0x30, 0x03, 0x02, 0x01, 0x05]` (an ASN.1 SEQUENCE
+        // wrapping INTEGER 5), used here purely as sample DER bytes.
+        let pem = b"-----BEGIN X509 CRL-----\nMAMCAQU=\n-----END X509 CRL-----\n";
+        assert_eq!(to_der(pem), [0x30, 0x03, 0x02, 0x01, 0x05]);
+    }
+
+    fn crl(issuer: &[u8], revoked_serials: &[&[u8]]) -> Crl {
+        Crl {
+            issuer: issuer.to_vec(),
+            revoked_serials: revoked_serials.iter().map(|s| s.to_vec()).collect(),
+        }
+    }
+
+    #[test]
+    fn is_revoked_matches_issuer_and_serial() {
+        let crls = vec![crl(b"issuer-a", &[b"\x01", b"\x02"])];
+        assert!(is_revoked(b"issuer-a", b"\x02", &crls));
+    }
+
+    #[test]
+    fn is_revoked_ignores_other_issuers() {
+        let crls = vec![crl(b"issuer-a", &[b"\x01"])];
+        assert!(!is_revoked(b"issuer-b", b"\x01", &crls));
+    }
+
+    #[test]
+    fn is_revoked_false_for_unlisted_serial() {
+        let crls = vec![crl(b"issuer-a", &[b"\x01"])];
+        assert!(!is_revoked(b"issuer-a", b"\x03", &crls));
     }
 }